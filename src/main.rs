@@ -1,6 +1,12 @@
-use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::{BTreeMap, HashMap};
+
+mod ordering;
+mod shuffle;
+
+use ordering::{count_rising_sequences, OccupancyMatrix};
+use shuffle::{parse_shuffle_spec, Shuffle};
 
 #[derive(Debug, Clone, Copy)]
 enum Color {
@@ -23,6 +29,9 @@ enum Value {
 struct Card {
     color: Color,
     value: Value,
+    /// Index of this card in the freshly generated, unshuffled deck; used
+    /// by the ordering metrics to measure how thoroughly a shuffle mixes.
+    origin: usize,
 }
 
 const NUM_WILD_CARDS: usize = 8;
@@ -37,12 +46,14 @@ fn generate_deck() -> Vec<Card> {
     for &color in &[Color::Red, Color::Blue, Color::Green, Color::Yellow] {
         for value in 1..=12 {
             deck.push(Card {
-                color: color,
+                color,
                 value: Value::Number(value),
+                origin: deck.len(),
             });
             deck.push(Card {
-                color: color,
+                color,
                 value: Value::Number(value),
+                origin: deck.len(),
             });
         }
     }
@@ -52,6 +63,7 @@ fn generate_deck() -> Vec<Card> {
         deck.push(Card {
             color: Color::Wild,
             value: Value::Wild,
+            origin: deck.len(),
         });
     }
 
@@ -59,60 +71,13 @@ fn generate_deck() -> Vec<Card> {
         deck.push(Card {
             color: Color::Skip,
             value: Value::Skip,
+            origin: deck.len(),
         });
     }
 
     deck
 }
 
-fn shuffle_deck(deck: &mut Vec<Card>, times: usize) {
-    let mut rng = thread_rng();
-    for _ in 0..times {
-        deck.shuffle(&mut rng);
-    }
-}
-
-fn riffle_shuffle(deck: &mut Vec<Card>) {
-    let mut rng = thread_rng();
-    let mid = rng.gen_range(deck.len() / 2 - 2..=deck.len() / 2 + 2);
-    let (left, right) = deck.split_at(mid);
-    let mut shuffled = Vec::with_capacity(deck.len());
-
-    let mut left_iter = left.iter();
-    let mut right_iter = right.iter();
-
-    while left_iter.len() > 0 || right_iter.len() > 0 {
-        let take_from_left = rng.gen_range(1..=3);
-        for _ in 0..take_from_left {
-            if let Some(l) = left_iter.next() {
-                shuffled.push(l.clone());
-            }
-        }
-
-        let take_from_right = rng.gen_range(1..=3);
-        for _ in 0..take_from_right {
-            if let Some(r) = right_iter.next() {
-                shuffled.push(r.clone());
-            }
-        }
-    }
-
-    *deck = shuffled;
-}
-
-fn overhand_shuffle(deck: &mut Vec<Card>, passes: usize) {
-    let mut rng = thread_rng();
-    for _ in 0..passes {
-        let mut shuffled = Vec::with_capacity(deck.len());
-        while !deck.is_empty() {
-            let chunk_size = rng.gen_range(1..=deck.len().min(10));
-            let chunk: Vec<Card> = deck.drain(0..chunk_size).collect();
-            shuffled.splice(0..0, chunk); // Insert chunk at the beginning
-        }
-        *deck = shuffled;
-    }
-}
-
 fn deal_hands(deck: &mut Vec<Card>, num_hands: usize, hand_size: usize) -> Vec<Vec<Card>> {
     let mut hands = vec![Vec::with_capacity(hand_size); num_hands];
 
@@ -183,59 +148,236 @@ fn analyze_randomness(hands: &[Vec<Card>]) -> HashMap<String, f64> {
     metrics
 }
 
-fn main() {
-    let iterations = 1000; // Configure number of iterations
-    let shuffle_times = 2; // Configure shuffle repetitions
+/// Shuffle pipeline to run when none is given on the command line: two
+/// overhand shuffles of three passes each, followed by a riffle.
+const DEFAULT_PIPELINE: &str = "overhand:3*2,riffle";
+
+/// Derives a per-worker seed from a base seed so that a fixed base seed
+/// always splits into the same per-worker streams, regardless of how many
+/// threads happen to run.
+fn worker_seed(base_seed: u64, worker: usize) -> u64 {
+    const GOLDEN_GAMMA: u64 = 0x9E3779B97F4A7C15;
+    base_seed ^ (worker as u64).wrapping_mul(GOLDEN_GAMMA)
+}
 
-    println!("{} 2xoverhand plus riffle", shuffle_times);
+/// The per-iteration results summed over a worker's share of the run:
+/// the hand-level frequency metrics plus the deck-level ordering metrics.
+struct WorkerResult {
+    metrics: HashMap<String, f64>,
+    rising_sequence_total: f64,
+    occupancy: OccupancyMatrix,
+    /// Rising-sequence count of each individual iteration, in the order
+    /// they ran; populated only when the caller asked for the series.
+    rising_sequence_series: Option<Vec<f64>>,
+}
 
-    let mut randomness_results = Vec::new();
+/// Runs `iterations` shuffle/deal/analyze trials with a freshly seeded RNG
+/// and sums the resulting metrics (not yet averaged) into one result. When
+/// `collect_series` is set, also records each iteration's rising-sequence
+/// count individually for callers that want the raw per-iteration series.
+fn run_iterations(
+    pipeline: &dyn Shuffle,
+    iterations: usize,
+    seed: u64,
+    deck_size: usize,
+    collect_series: bool,
+) -> WorkerResult {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut summed_metrics = HashMap::new();
+    let mut rising_sequence_total = 0.0;
+    let mut occupancy = OccupancyMatrix::new(deck_size);
+    let mut rising_sequence_series = collect_series.then(Vec::new);
 
     for _ in 0..iterations {
         let mut deck = generate_deck();
-	//shuffle_deck(&mut deck, 5);
+        pipeline.apply(&mut deck, &mut rng);
 
-        //for _ in 0..shuffle_times {
-        //    overhand_shuffle(&mut deck, 3); // Example: Use overhand shuffle with 3 passes
-        //    overhand_shuffle(&mut deck, 3); // Example: Use overhand shuffle with 3 passes
-        //    riffle_shuffle(&mut deck);
-        //}
+        let rising_sequences = count_rising_sequences(&deck) as f64;
+        rising_sequence_total += rising_sequences;
+        if let Some(series) = &mut rising_sequence_series {
+            series.push(rising_sequences);
+        }
+        occupancy.record(&deck);
 
         let hands = deal_hands(&mut deck, NUM_HANDS, HAND_SIZE);
         let metrics = analyze_randomness(&hands);
 
-        randomness_results.push(metrics);
+        for (key, value) in metrics {
+            *summed_metrics.entry(key).or_insert(0.0) += value;
+        }
     }
 
-    // Aggregate metrics
-    let mut aggregated_metrics = HashMap::new();
-    let mut total_color_entropy = 0.0;
-    let mut total_value_entropy = 0.0;
+    WorkerResult {
+        metrics: summed_metrics,
+        rising_sequence_total,
+        occupancy,
+        rising_sequence_series,
+    }
+}
 
-    for result in &randomness_results {
-        for (key, value) in result {
-            *aggregated_metrics.entry(key.clone()).or_insert(0.0) += value;
+/// Splits `iterations` as evenly as possible across up to `threads` chunks:
+/// the first `iterations % threads` chunks get one extra iteration, and
+/// empty chunks are dropped, so fewer than `threads` chunks come back when
+/// `iterations < threads`.
+fn split_iterations(iterations: usize, threads: usize) -> Vec<usize> {
+    let base = iterations / threads;
+    let remainder = iterations % threads;
+    (0..threads)
+        .map(|i| base + if i < remainder { 1 } else { 0 })
+        .filter(|&chunk| chunk > 0)
+        .collect()
+}
 
-            if key == "Color Entropy" {
-                total_color_entropy += value;
-            }
-            if key == "Value Entropy" {
-                total_value_entropy += value;
-            }
+#[derive(clap::Parser, Debug)]
+#[command(about = "Simulates deck shuffles and analyzes how random they are")]
+struct Cli {
+    /// Shuffle pipeline to run, e.g. "riffle", "overhand:3*2,riffle", or "fisher-yates:7"
+    #[arg(short = 'p', long, default_value = DEFAULT_PIPELINE)]
+    pipeline: String,
+
+    /// Number of Monte-Carlo iterations to run
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    iterations: usize,
+
+    /// Seed for the RNG driving every shuffle and deal; omit for a random seed
+    #[arg(short = 's', long)]
+    seed: Option<u64>,
+
+    /// Number of worker threads to split the iterations across
+    #[arg(short = 't', long, default_value_t = 1)]
+    threads: usize,
+
+    /// Emit the full run as a single JSON document instead of text
+    #[arg(short = 'j', long)]
+    json: bool,
+
+    /// Include the raw per-iteration rising-sequence series in --json output
+    #[arg(long, requires = "json")]
+    per_iteration: bool,
+}
+
+/// The full result of a run: its configuration plus every aggregated
+/// metric, serialized as a stable JSON document so two shuffle pipelines
+/// can be compared programmatically.
+#[derive(serde::Serialize)]
+struct RunReport {
+    pipeline: String,
+    seed: u64,
+    iterations: usize,
+    threads: usize,
+    deck_size: usize,
+    num_hands: usize,
+    hand_size: usize,
+    frequency_metrics: BTreeMap<String, f64>,
+    average_color_entropy: f64,
+    average_value_entropy: f64,
+    mean_rising_sequences: f64,
+    position_chi_square: ordering::ChiSquareResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_iteration_rising_sequences: Option<Vec<f64>>,
+}
+
+fn main() {
+    use clap::Parser;
+
+    let cli = Cli::parse();
+    let pipeline = parse_shuffle_spec(&cli.pipeline).unwrap_or_else(|err| {
+        eprintln!("invalid --pipeline '{}': {}", cli.pipeline, err);
+        std::process::exit(1);
+    });
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    let pipeline = pipeline.as_ref();
+    let deck_size = generate_deck().len();
+
+    if !cli.json {
+        println!("Shuffle pipeline: {}", cli.pipeline);
+        println!("Seed: {}", seed);
+    }
+
+    let chunks = split_iterations(cli.iterations, cli.threads.max(1));
+    let worker_results = crossbeam::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(worker, &chunk)| {
+                let worker_seed = worker_seed(seed, worker);
+                scope.spawn(move |_| {
+                    run_iterations(pipeline, chunk, worker_seed, deck_size, cli.per_iteration)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("shuffle worker thread panicked"))
+            .collect::<Vec<_>>()
+    })
+    .expect("shuffle worker pool panicked");
+
+    // Aggregate metrics
+    let mut aggregated_metrics = HashMap::new();
+    let mut rising_sequence_total = 0.0;
+    let mut occupancy = OccupancyMatrix::new(deck_size);
+    let mut rising_sequence_series = cli.per_iteration.then(Vec::new);
+    for result in worker_results {
+        for (key, value) in result.metrics {
+            *aggregated_metrics.entry(key).or_insert(0.0) += value;
+        }
+        rising_sequence_total += result.rising_sequence_total;
+        occupancy.merge(&result.occupancy);
+        if let Some(series) = &mut rising_sequence_series {
+            series.extend(result.rising_sequence_series.unwrap_or_default());
         }
     }
 
     // Compute overall metrics
-    let avg_color_entropy = total_color_entropy / iterations as f64;
-    let avg_value_entropy = total_value_entropy / iterations as f64;
+    let avg_color_entropy = aggregated_metrics.get("Color Entropy").unwrap_or(&0.0) / cli.iterations as f64;
+    let avg_value_entropy = aggregated_metrics.get("Value Entropy").unwrap_or(&0.0) / cli.iterations as f64;
+    let mean_rising_sequences = rising_sequence_total / cli.iterations as f64;
+    let chi_square = occupancy.chi_square();
+
+    let frequency_metrics: BTreeMap<String, f64> = aggregated_metrics
+        .iter()
+        .map(|(key, total_value)| (key.clone(), total_value / cli.iterations as f64))
+        .collect();
+
+    if cli.json {
+        let report = RunReport {
+            pipeline: cli.pipeline,
+            seed,
+            iterations: cli.iterations,
+            threads: cli.threads,
+            deck_size,
+            num_hands: NUM_HANDS,
+            hand_size: HAND_SIZE,
+            frequency_metrics,
+            average_color_entropy: avg_color_entropy,
+            average_value_entropy: avg_value_entropy,
+            mean_rising_sequences,
+            position_chi_square: chi_square,
+            per_iteration_rising_sequences: rising_sequence_series,
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("run report is serializable")
+        );
+        return;
+    }
 
-    println!("Randomness Analysis over {} iterations:", iterations);
-    for (key, total_value) in &aggregated_metrics {
-        println!("{}: {:.4}", key, total_value / iterations as f64);
+    println!("Randomness Analysis over {} iterations:", cli.iterations);
+    for (key, value) in &frequency_metrics {
+        println!("{}: {:.4}", key, value);
     }
 
     println!("\nOverall Metrics:");
     println!("Average Color Entropy: {:.4}", avg_color_entropy);
     println!("Average Value Entropy: {:.4}", avg_value_entropy);
+
+    println!("\nOrdering Metrics:");
+    println!("Mean rising sequences: {:.4} (deck size {})", mean_rising_sequences, deck_size);
+    println!(
+        "Position chi-square: statistic={:.4}, dof={:.0}, p≈{:.4}",
+        chi_square.statistic, chi_square.degrees_of_freedom, chi_square.p_value
+    );
 }
 
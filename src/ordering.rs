@@ -0,0 +1,203 @@
+use crate::Card;
+
+/// Counts the maximal rising sequences in a shuffled deck: runs of
+/// consecutive original indices (0, 1, 2, ...) that appear in increasing
+/// position order in the shuffled deck. An untouched deck has exactly 1
+/// rising sequence; a single GSR riffle of two packets yields at most 2.
+pub fn count_rising_sequences(deck: &[Card]) -> usize {
+    if deck.is_empty() {
+        return 0;
+    }
+
+    let mut position = vec![0usize; deck.len()];
+    for (pos, card) in deck.iter().enumerate() {
+        position[card.origin] = pos;
+    }
+
+    1 + position
+        .windows(2)
+        .filter(|pair| pair[0] > pair[1])
+        .count()
+}
+
+/// Accumulates, across many iterations, how often each original card
+/// lands in each shuffled position, so the landing-position histogram of
+/// every card can be tested against the uniform expectation.
+pub struct OccupancyMatrix {
+    n: usize,
+    iterations: u64,
+    /// `counts[position][original_index]`
+    counts: Vec<Vec<u64>>,
+}
+
+impl OccupancyMatrix {
+    pub fn new(n: usize) -> Self {
+        OccupancyMatrix {
+            n,
+            iterations: 0,
+            counts: vec![vec![0; n]; n],
+        }
+    }
+
+    pub fn record(&mut self, deck: &[Card]) {
+        for (position, card) in deck.iter().enumerate() {
+            self.counts[position][card.origin] += 1;
+        }
+        self.iterations += 1;
+    }
+
+    pub fn merge(&mut self, other: &OccupancyMatrix) {
+        for (row, other_row) in self.counts.iter_mut().zip(&other.counts) {
+            for (count, other_count) in row.iter_mut().zip(other_row) {
+                *count += other_count;
+            }
+        }
+        self.iterations += other.iterations;
+    }
+
+    /// Runs a chi-square goodness-of-fit of each original card's
+    /// landing-position histogram against the uniform expectation
+    /// `iterations / n`, summed over all `n` cards, with an approximate
+    /// p-value from the Wilson-Hilferty transform.
+    pub fn chi_square(&self) -> ChiSquareResult {
+        let expected = self.iterations as f64 / self.n as f64;
+
+        let statistic: f64 = (0..self.n)
+            .map(|original_index| {
+                (0..self.n)
+                    .map(|position| {
+                        let observed = self.counts[position][original_index] as f64;
+                        let diff = observed - expected;
+                        diff * diff / expected
+                    })
+                    .sum::<f64>()
+            })
+            .sum();
+
+        // The occupancy table is a permutation-matrix aggregate: every
+        // iteration fixes both marginals (each position holds exactly one
+        // card, and each card lands in exactly one position), so this is
+        // a joint uniformity test over an (n-1) x (n-1) free table, not
+        // n independent (n-1)-dof tests.
+        let degrees_of_freedom = ((self.n - 1) * (self.n - 1)) as f64;
+        let p_value = chi_square_upper_tail_p_value(statistic, degrees_of_freedom);
+
+        ChiSquareResult {
+            statistic,
+            degrees_of_freedom,
+            p_value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct ChiSquareResult {
+    pub statistic: f64,
+    pub degrees_of_freedom: f64,
+    pub p_value: f64,
+}
+
+/// Approximates `P(X >= statistic)` for `X ~ ChiSquare(degrees_of_freedom)`
+/// via the Wilson-Hilferty cube-root transform, which maps a chi-square
+/// variable onto an approximately standard normal one.
+fn chi_square_upper_tail_p_value(statistic: f64, degrees_of_freedom: f64) -> f64 {
+    let k = degrees_of_freedom;
+    let z = ((statistic / k).powf(1.0 / 3.0) - (1.0 - 2.0 / (9.0 * k))) / (2.0 / (9.0 * k)).sqrt();
+    1.0 - standard_normal_cdf(z)
+}
+
+/// Abramowitz-Stegun approximation of the standard normal CDF (formula
+/// 7.1.26), accurate to about 7.5e-8.
+fn standard_normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+
+    let a1 = 0.254829592;
+    let a2 = -0.284496736;
+    let a3 = 1.421413741;
+    let a4 = -1.453152027;
+    let a5 = 1.061405429;
+    let p = 0.3275911;
+
+    let t = 1.0 / (1.0 + p * x);
+    let y = 1.0 - (((((a5 * t + a4) * t) + a3) * t + a2) * t + a1) * t * (-x * x).exp();
+
+    sign * y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Card, Color, Value};
+
+    fn card(origin: usize) -> Card {
+        Card {
+            color: Color::Wild,
+            value: Value::Wild,
+            origin,
+        }
+    }
+
+    #[test]
+    fn identity_deck_has_one_rising_sequence() {
+        let deck: Vec<Card> = (0..10).map(card).collect();
+        assert_eq!(count_rising_sequences(&deck), 1);
+    }
+
+    #[test]
+    fn reversed_deck_has_n_rising_sequences() {
+        let deck: Vec<Card> = (0..10).rev().map(card).collect();
+        assert_eq!(count_rising_sequences(&deck), 10);
+    }
+
+    #[test]
+    fn empty_deck_has_no_rising_sequences() {
+        let deck: Vec<Card> = Vec::new();
+        assert_eq!(count_rising_sequences(&deck), 0);
+    }
+
+    #[test]
+    fn non_monotone_deck_counts_runs_of_consecutive_origins() {
+        let deck: Vec<Card> = [1, 3, 0, 2].into_iter().map(card).collect();
+        assert_eq!(count_rising_sequences(&deck), 3);
+    }
+
+    #[test]
+    fn one_riffle_interleave_has_two_rising_sequences() {
+        let deck: Vec<Card> = [0, 3, 1, 4, 2, 5].into_iter().map(card).collect();
+        assert_eq!(count_rising_sequences(&deck), 2);
+    }
+
+    #[test]
+    fn degrees_of_freedom_is_n_minus_one_squared() {
+        let n = 5;
+        let deck: Vec<Card> = (0..n).map(card).collect();
+        let mut occupancy = OccupancyMatrix::new(n);
+        occupancy.record(&deck);
+
+        let result = occupancy.chi_square();
+
+        assert_eq!(result.degrees_of_freedom, ((n - 1) * (n - 1)) as f64);
+    }
+
+    #[test]
+    fn merge_sums_occupancy_counts() {
+        let n = 4;
+        let deck: Vec<Card> = (0..n).map(card).collect();
+        let mut a = OccupancyMatrix::new(n);
+        a.record(&deck);
+        let mut b = OccupancyMatrix::new(n);
+        b.record(&deck);
+
+        a.merge(&b);
+
+        assert_eq!(a.iterations, 2);
+        for i in 0..n {
+            assert_eq!(a.counts[i][i], 2);
+        }
+    }
+}
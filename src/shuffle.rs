@@ -0,0 +1,215 @@
+use rand::{Rng, RngCore};
+
+use crate::Card;
+
+/// A shuffling algorithm that can be applied to a deck in place.
+///
+/// Implementors are meant to be cheap to construct and composed via
+/// [`Composite`], so a full shuffling routine (e.g. "two overhand passes
+/// followed by a riffle") is ordinary data rather than code in `main`.
+pub trait Shuffle: Send + Sync {
+    fn apply(&self, deck: &mut Vec<Card>, rng: &mut dyn RngCore);
+}
+
+/// Repeated Fisher-Yates shuffles via `rand::seq::SliceRandom::shuffle`.
+pub struct FisherYates {
+    pub times: usize,
+}
+
+impl Shuffle for FisherYates {
+    fn apply(&self, deck: &mut Vec<Card>, rng: &mut dyn RngCore) {
+        use rand::seq::SliceRandom;
+        for _ in 0..self.times {
+            deck.shuffle(rng);
+        }
+    }
+}
+
+/// A riffle shuffle following the Gilbert-Shannon-Reeds (GSR) model: the
+/// cut point is drawn from the binomial distribution a physical riffle
+/// actually produces, and the two packets are interleaved card-by-card
+/// with probability proportional to each packet's remaining size.
+pub struct Riffle;
+
+impl Riffle {
+    /// Draws a GSR cut point for an `n`-card deck by flipping `n` fair
+    /// coins and counting heads, i.e. sampling `Binomial(n, 1/2)`.
+    fn gsr_cut(n: usize, rng: &mut dyn RngCore) -> usize {
+        (0..n).filter(|_| rng.gen_bool(0.5)).count()
+    }
+}
+
+impl Shuffle for Riffle {
+    fn apply(&self, deck: &mut Vec<Card>, rng: &mut dyn RngCore) {
+        let cut = Self::gsr_cut(deck.len(), rng);
+        let (top, bottom) = deck.split_at(cut);
+        let mut shuffled = Vec::with_capacity(deck.len());
+
+        let mut top_iter = top.iter().peekable();
+        let mut bottom_iter = bottom.iter().peekable();
+
+        while top_iter.peek().is_some() || bottom_iter.peek().is_some() {
+            let a = top_iter.len();
+            let b = bottom_iter.len();
+            // Drop the next card from the top packet with probability
+            // a/(a+b), from the bottom packet with probability b/(a+b).
+            let take_from_top = rng.gen_range(0..a + b) < a;
+
+            let card = if take_from_top {
+                top_iter.next()
+            } else {
+                bottom_iter.next()
+            };
+            shuffled.push(card.expect("packet is non-empty").clone());
+        }
+
+        *deck = shuffled;
+    }
+}
+
+/// An overhand shuffle: repeatedly cut a small chunk off the top of the
+/// deck and place it in front of what's already been moved.
+pub struct Overhand {
+    pub passes: usize,
+}
+
+impl Shuffle for Overhand {
+    fn apply(&self, deck: &mut Vec<Card>, rng: &mut dyn RngCore) {
+        for _ in 0..self.passes {
+            let mut shuffled = Vec::with_capacity(deck.len());
+            while !deck.is_empty() {
+                let chunk_size = rng.gen_range(1..=deck.len().min(10));
+                let chunk: Vec<Card> = deck.drain(0..chunk_size).collect();
+                shuffled.splice(0..0, chunk); // Insert chunk at the beginning
+            }
+            *deck = shuffled;
+        }
+    }
+}
+
+/// Chains other [`Shuffle`] implementors, applying each in sequence.
+pub struct Composite {
+    pub steps: Vec<Box<dyn Shuffle>>,
+}
+
+impl Shuffle for Composite {
+    fn apply(&self, deck: &mut Vec<Card>, rng: &mut dyn RngCore) {
+        for step in &self.steps {
+            step.apply(deck, rng);
+        }
+    }
+}
+
+fn build_step(name: &str, arg: Option<&str>) -> Result<Box<dyn Shuffle>, String> {
+    let parse_count = |default: usize| -> Result<usize, String> {
+        match arg {
+            Some(a) => a
+                .parse::<usize>()
+                .map_err(|_| format!("invalid count '{}' for shuffle '{}'", a, name)),
+            None => Ok(default),
+        }
+    };
+
+    match name {
+        "riffle" => match arg {
+            None => Ok(Box::new(Riffle)),
+            Some(a) => Err(format!(
+                "shuffle 'riffle' takes no argument, got 'riffle:{}' (did you mean 'riffle*{}'?)",
+                a, a
+            )),
+        },
+        "overhand" => Ok(Box::new(Overhand {
+            passes: parse_count(1)?,
+        })),
+        "fisher-yates" => Ok(Box::new(FisherYates {
+            times: parse_count(1)?,
+        })),
+        other => Err(format!("unknown shuffle algorithm '{}'", other)),
+    }
+}
+
+/// Parses a shuffle pipeline specification such as `"riffle"`,
+/// `"overhand:3"`, `"fisher-yates:5"`, or `"overhand:3*2,riffle"` into a
+/// boxed [`Shuffle`], so a pipeline can be chosen at runtime instead of
+/// edited into `main`.
+///
+/// Each comma-separated term is `name[:arg][*repeat]`: `name` selects the
+/// algorithm, `arg` is its pass/shuffle count, and `*repeat` repeats that
+/// term `repeat` times in the resulting pipeline (e.g. `"riffle*7"` runs
+/// seven sequential riffles).
+pub fn parse_shuffle_spec(spec: &str) -> Result<Box<dyn Shuffle>, String> {
+    let mut steps: Vec<Box<dyn Shuffle>> = Vec::new();
+
+    for term in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (body, repeat) = match term.split_once('*') {
+            Some((body, repeat)) => (
+                body,
+                repeat
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid repeat count in '{}'", term))?,
+            ),
+            None => (term, 1),
+        };
+
+        let (name, arg) = match body.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (body, None),
+        };
+
+        for _ in 0..repeat {
+            steps.push(build_step(name, arg)?);
+        }
+    }
+
+    match steps.len() {
+        0 => Err("empty shuffle specification".to_string()),
+        1 => Ok(steps.remove(0)),
+        _ => Ok(Box::new(Composite { steps })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn riffle_rejects_an_argument() {
+        assert!(parse_shuffle_spec("riffle:5").is_err());
+    }
+
+    #[test]
+    fn empty_spec_is_rejected() {
+        assert!(parse_shuffle_spec("").is_err());
+        assert!(parse_shuffle_spec("   ").is_err());
+    }
+
+    #[test]
+    fn bad_repeat_count_is_rejected() {
+        assert!(parse_shuffle_spec("riffle*nope").is_err());
+    }
+
+    #[test]
+    fn unknown_algorithm_is_rejected() {
+        assert!(parse_shuffle_spec("shuffle-o-matic").is_err());
+    }
+
+    #[test]
+    fn single_terms_parse() {
+        assert!(parse_shuffle_spec("riffle").is_ok());
+        assert!(parse_shuffle_spec("overhand:3").is_ok());
+        assert!(parse_shuffle_spec("fisher-yates:5").is_ok());
+    }
+
+    #[test]
+    fn composite_pipeline_preserves_deck_size() {
+        let pipeline = parse_shuffle_spec("overhand:3*2,riffle").unwrap();
+        let mut deck = crate::generate_deck();
+        let original_len = deck.len();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        pipeline.apply(&mut deck, &mut rng);
+
+        assert_eq!(deck.len(), original_len);
+    }
+}